@@ -0,0 +1,44 @@
+use aes::Aes256;
+use aes::cipher::KeyInit;
+use criterion::{criterion_group, criterion_main, Criterion};
+use eme::{Eme, eme_32_aes_enc};
+
+/// `eme_32_aes_enc` schedules the AES-256 key fresh on every call (one `Aes256::new_from_slice` +
+/// one `Eme::new` per call); this benchmarks that call directly, for comparison against
+/// `eme_encrypt_cached`, which schedules the key once up front and reuses the same `Eme` for every
+/// iteration.
+fn eme_32_aes_enc_unkeyed(c: &mut Criterion)
+{
+    let k = [0u8;32];
+    let t = [0u8;16];
+    let p = [0u8;512];
+
+    c.bench_function("eme_32_aes_enc (rekeys every call)", |b| {
+        b.iter(|| {
+            let mut out = [0u8;512];
+            eme_32_aes_enc(&mut out, &k, &t, &p);
+            out
+        })
+    });
+}
+
+fn eme_encrypt_cached(c: &mut Criterion)
+{
+    let k = [0u8;32];
+    let t = [0u8;16];
+    let p = [0u8;512];
+
+    let cipher = Aes256::new_from_slice(&k).unwrap();
+    let eme = Eme::new(cipher);
+
+    c.bench_function("Eme::encrypt (cached key schedule)", |b| {
+        b.iter(|| {
+            let mut buf = p;
+            eme.encrypt(&t, &mut buf).unwrap();
+            buf
+        })
+    });
+}
+
+criterion_group!(benches, eme_32_aes_enc_unkeyed, eme_encrypt_cached);
+criterion_main!(benches);
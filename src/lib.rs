@@ -1,87 +1,155 @@
 #![no_std]
 
-/// EME (ECB-Mix-ECB) constructs a block cipher with a larger block size from a block cipher with a
-/// smaller block size.
-///
-/// It uses a block cipher to create a tweakable cipher.
-///
-/// C: cipher text
-/// P: public text
-/// K: secret key
-/// T: tweak
-///
-/// C = E(T, K, P)
-/// P = D(T, K, C)
-///
-/// k: bits in secret key (K)
-/// n: bits per block in the chosen block cipher. Also specifies the used GF(2**n) field.
-/// mn: plain text & cipher text size
-/// m: tweak bits, number of blocks in E,D used
-///
-/// m is one of 1..n
-///
-/// EME-32-AES is a specification of EME with parameters fixed:
-///
-///  - E,D = aes-256-cbc
-///  - n = 128 (bits), 16 bytes
-///  - m = 32
-///  - (derived) message (text) size = 512 bytes
-///
-/// Our implimentation has the following fixed parameters:
-///
-///  - n = 128 (bits), 16 bytes
-///
-///
-/// [EME-32-AES draft spec](http://grouper.ieee.org/groups/1619/email/pdf00020.pdf)
+//! EME (ECB-Mix-ECB) constructs a block cipher with a larger block size from a block cipher with a
+//! smaller block size.
+//!
+//! It uses a block cipher to create a tweakable cipher.
+//!
+//! C: cipher text
+//! P: public text
+//! K: secret key
+//! T: tweak
+//!
+//! C = E(T, K, P)
+//! P = D(T, K, C)
+//!
+//! k: bits in secret key (K)
+//! n: bits per block in the chosen block cipher. Also specifies the used GF(2**n) field.
+//! mn: plain text & cipher text size
+//! m: tweak bits, number of blocks in E,D used
+//!
+//! m is one of 1..n
+//!
+//! EME-32-AES is a specification of EME with parameters fixed:
+//!
+//!  - E,D = aes-256-cbc
+//!  - n = 128 (bits), 16 bytes
+//!  - m = 32
+//!  - (derived) message (text) size = 512 bytes
+//!
+//! Our implimentation has the following fixed parameters:
+//!
+//!  - n = 128 (bits), 16 bytes
+//!
+//!
+//! [EME-32-AES draft spec](http://grouper.ieee.org/groups/1619/email/pdf00020.pdf)
 
-extern crate aesti;
+extern crate aes;
+extern crate cipher;
 
-//extern crate generic_array;
-//use generic_array::{ArrayLength,GenericArray};
+use cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+use cipher::generic_array::GenericArray;
+use cipher::consts::U16;
+use aes::Aes256;
 
 #[macro_use]
 extern crate index_fixed;
 
-/*
-trait Block {
-    type BlockSize: ArrayLength<u8>;
+/// GF(2**128) doubling, as used by the EME-32-AES draft spec's `multByTwo` procedure.
+pub mod gf128 {
+    /// Doubles a 128-bit block in place, reducing by the polynomial `x^128 + x^7 + x^2 + x + 1`
+    /// (0x87) on overflow, per the EME-32-AES draft spec's `multByTwo` procedure.
+    pub fn double(block: &mut [u8;16])
+    {
+        let carry = block[15] >= 128;
+        for j in (1..16).rev() {
+            let carry_in = block[j-1] >= 128;
+            block[j] = block[j].wrapping_mul(2);
+            if carry_in {
+                block[j] = block[j].wrapping_add(1);
+            }
+        }
+        block[0] = block[0].wrapping_mul(2);
+        if carry {
+            block[0] ^= 0x87;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::double;
+
+        #[test]
+        fn doubles_all_ones_block() {
+            let mut b = [0xffu8;16];
+            double(&mut b);
+            // top bit of byte 15 was set, so doubling reduces by 0x87 in byte 0; every other
+            // byte carries in a 1 from its lower neighbour (also 0xff), so stays 0xff.
+            let mut expect = [0xffu8;16];
+            expect[0] = 0xfe ^ 0x87;
+            assert_eq!(b, expect);
+        }
+
+        #[test]
+        fn doubles_high_bit_block() {
+            let mut b = [0u8;16];
+            b[15] = 0x80;
+            double(&mut b);
+            // doubling the lone high bit of the top byte overflows, reducing by 0x87.
+            let mut expect = [0u8;16];
+            expect[15] = 0;
+            expect[0] = 0x87;
+            assert_eq!(b, expect);
+        }
+
+        #[test]
+        fn successive_doublings_match_known_powers_of_two() {
+            // doubling 1 (stored as byte 0 == 1) twice should give 4, four times should give
+            // 16, etc., as long as no reduction is triggered.
+            let mut b = [0u8;16];
+            b[0] = 1;
+            for power in 1..8 {
+                double(&mut b);
+                let mut expect = [0u8;16];
+                expect[0] = 1 << power;
+                assert_eq!(b, expect, "after {} doublings", power);
+            }
+        }
+
+        #[test]
+        fn doubling_many_times_never_panics_and_stays_deterministic() {
+            let mut a = [0x42u8;16];
+            let mut b = [0x42u8;16];
+            for _ in 0..1000 {
+                double(&mut a);
+                double(&mut b);
+                assert_eq!(a, b);
+            }
+        }
+    }
 }
-*/
 
 /// Multiply by 2 in GF(2**128)
-///
-/// multByTwo proceedure from the EME-32-AES draft spec
 fn mult_by_2(out: &mut [u8;16], input: &[u8;16])
 {
-    out[0] = 2 * input[0];
-    if input[15] >= 128 {
-        out[0] ^= 135;
-    }
-
-    for j in 1..16 {
-        out[j] = 2 * input[j];
-        if input[j-1] >= 128 {
-            out[j] += 1;
-        }
-    }
+    *out = *input;
+    gf128::double(out);
 }
 
 fn mult_by_2_ip(out: &mut [u8;16])
 {
-    let x = out.clone();
-    mult_by_2(out, &x);
+    gf128::double(out);
 }
 
-fn encrypt_aes(out: &mut [u8;16], k: &[u8], input: &[u8;16])
-{
-    let aes = aesti::Aes::with_key(k).unwrap();
-    aes.encrypt(out, input);
+/// Direction of the EME transform. The two directions share the exact same structure; only
+/// which cipher primitive is used in the two outer ECB passes (and in the central single-block
+/// step) differs. See `transform`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Dir {
+    Enc,
+    Dec,
 }
 
-fn encrypt_aes_ip(out: &mut [u8;16], k: &[u8])
+/// Runs the block cipher underlying an `Eme<C>` over a single 16-byte block, in place, in
+/// direction `dir`.
+fn block_cipher_ip<C>(dir: Dir, cipher: &C, block: &mut [u8;16])
+where C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>
 {
-    let x = out.clone();
-    encrypt_aes(out, k, &x);
+    let block = GenericArray::from_mut_slice(block);
+    match dir {
+        Dir::Enc => cipher.encrypt_block(block),
+        Dir::Dec => cipher.decrypt_block(block),
+    }
 }
 
 fn xor_blocks(out: &mut [u8;16], in1: &[u8;16], in2: &[u8;16])
@@ -94,56 +162,226 @@ fn xor_blocks(out: &mut [u8;16], in1: &[u8;16], in2: &[u8;16])
 fn xor_blocks_ip(out: &mut [u8;16], in2: &[u8;16])
 {
     for (a, b) in out.iter_mut().zip(in2.iter()) {
-        *a = *a ^ b;
+        *a ^= b;
     }
 }
 
-pub fn eme_32_aes_enc(c: &mut [u8;512], k: &[u8], t: &[u8;16], p: &[u8;512])
+/// Largest number of 16-byte blocks EME supports in a single message (`m` in the spec, `m` is
+/// one of `1..n` and the draft fixes `n = 128`, i.e. up to 128 blocks / 2048 bytes).
+pub const MAX_BLOCKS: usize = 128;
+
+/// A message length isn't a valid EME input.
+///
+/// EME requires the message to be a whole number of 16-byte blocks, with at least one and no
+/// more than [`MAX_BLOCKS`] of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidLength;
+
+/// Checks `len` is a valid EME message length (in bytes) and returns the block count.
+fn check_len(len: usize) -> Result<usize, InvalidLength>
+{
+    if !len.is_multiple_of(16) {
+        return Err(InvalidLength);
+    }
+    let nblocks = len / 16;
+    if !(1..=MAX_BLOCKS).contains(&nblocks) {
+        return Err(InvalidLength);
+    }
+    Ok(nblocks)
+}
+
+/// Shared core of `Eme::encrypt`/`Eme::decrypt`.
+///
+/// Both directions follow the identical ECB-mix-ECB structure; per the EME spec they differ in
+/// exactly three places: the two outer per-block ECB passes use the cipher's decrypt operation
+/// instead of encrypt, and the central single-block step derives `mc` from `mp` (the xor-sum of
+/// the first ECB pass) using the cipher's decrypt operation instead of encrypt -- `mp` itself is
+/// left untouched either way. The mask `l = 2*E(k; 0)` is always derived with encryption,
+/// regardless of direction.
+///
+/// `buf` is `nblocks * 16` bytes long and is transformed in place. `zero` is `E(k; 0)`,
+/// precomputed once per `Eme<C>` rather than once per call.
+fn transform<C>(dir: Dir, cipher: &C, zero: &[u8;16], t: &[u8;16], buf: &mut [u8], nblocks: usize)
+where C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>
 {
-    let mut l = [0u8;16];
     let mut m = [0u8;16];
     let mut mp = [0u8;16];
-    let mut mc = [0u8;16];
-
-    let mut zero = [0u8;16];
 
-    encrypt_aes_ip(&mut zero, k);                  /* set l = 2*AES-enc(k; 0) */
-    mult_by_2(&mut l, &zero);
+    /* L[0] = 2*E(k; 0), L[j] = 2*L[j-1] -- precomputed so both ECB passes below just iterate
+     * the table instead of mutating a single running mask. */
+    let mut l_table = [[0u8;16]; MAX_BLOCKS];
+    mult_by_2(&mut l_table[0], zero);
+    for j in 1..nblocks {
+        let (prev, next) = l_table[..j+1].split_at_mut(j);
+        mult_by_2(&mut next[0], &prev[j-1]);
+    }
 
-    for j in 0..32 {
-        xor_blocks(index_fixed!(&mut c[j*16..]; .. 16),
-                   index_fixed!(&p[j*16..]; .. 16),
-                   &l);
-        encrypt_aes_ip(index_fixed!(&mut c[j*16..];..16), k);  /* PPPj = AES-enc(k; PPj)  */
-        mult_by_2_ip(&mut l);
+    for j in 0..nblocks {
+        xor_blocks_ip(index_fixed!(&mut buf[j*16..]; .. 16), &l_table[j]);  /* PPj = Pj xor l */
+        block_cipher_ip(dir, cipher, index_fixed!(&mut buf[j*16..];..16));  /* PPPj = C(k; PPj) */
+    }
+    xor_blocks(&mut mp, index_fixed!(&buf[0..]; ..16), t);                  /* sum =(xorSum PPPj) xor t */
+    for j in 1..nblocks {
+        xor_blocks_ip(&mut mp, index_fixed!(&buf[j*16..];..16));
     }
-    xor_blocks(&mut mp, index_fixed!(&mut c;..16), t);                     /* mp =(xorSum PPPj) xor t */
-    for j in 1..32 {
-        xor_blocks_ip(&mut mp, index_fixed!(&c[j*16..];..16));
+    let mut mc = mp;
+    match dir {
+        Dir::Enc => cipher.encrypt_block(GenericArray::from_mut_slice(&mut mc)),  /* mc = E(k; mp) */
+        Dir::Dec => cipher.decrypt_block(GenericArray::from_mut_slice(&mut mc)),  /* mc = D(k; sum) */
     }
-    encrypt_aes(&mut mc, k, &mp);                      /* mc = AES-enc(k; mp)     */
     xor_blocks(&mut m, &mp, &mc);                       /* m = mp xor mc           */
-    for j in 1..32 {
+    for j in 1..nblocks {
         mult_by_2_ip(&mut m);
-        xor_blocks_ip(index_fixed!(&mut c[j*16..];..16), &m);  /* CCCj = 2**(j-1)*m xor PPPj */
+        xor_blocks_ip(index_fixed!(&mut buf[j*16..];..16), &m);  /* CCCj = 2**(j-1)*m xor PPPj */
+    }
+    xor_blocks(index_fixed!(&mut buf[0..]; .. 16), &mc, t);       /* CCC1 = (xorSum CCCj) xor t xor mc */
+
+    {
+        let (first, rest) = buf.split_at_mut(16);
+        let first = index_fixed!(&mut first;..16);
+        for j in 1..nblocks {
+            xor_blocks_ip(first, index_fixed!(&rest[(j-1)*16..]; .. 16));
+        }
+    }
+    for j in 0..nblocks {
+        block_cipher_ip(dir, cipher, index_fixed!(&mut buf[j*16..];..16));  /* CCj = C(k; CCCj)  */
+        xor_blocks_ip(index_fixed!(&mut buf[j*16..];.. 16), &l_table[j]);  /* Cj = 2**(j-1)*l xor CCj */
+    }
+}
+
+/// EME (ECB-Mix-ECB) over an arbitrary 128-bit block cipher `C`.
+///
+/// Holds an already key-scheduled cipher instance, plus the `E(k; 0)` mask derived from it, so
+/// that constructing an `Eme` does the key schedule and the `E(k; 0)` encryption once. Each
+/// `encrypt`/`decrypt` call still rebuilds the doubled mask table from the cached `E(k; 0)`
+/// (GF(2**128) doublings only, no further cipher calls). This is the same shape RustCrypto's own
+/// cipher/mode types use: build the context once from a key, then reuse it across many calls.
+pub struct Eme<C> {
+    cipher: C,
+    zero: [u8;16],
+}
+
+impl<C> Eme<C>
+where C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>
+{
+    pub fn new(cipher: C) -> Self
+    {
+        let mut zero = [0u8;16];
+        cipher.encrypt_block(GenericArray::from_mut_slice(&mut zero));  /* E(k; 0), cached */
+        Eme { cipher, zero }
+    }
+
+    /// Encrypts `buf` in place under tweak `t`.
+    ///
+    /// `buf` must be a non-zero multiple of 16 bytes, no larger than `MAX_BLOCKS * 16` bytes.
+    pub fn encrypt(&self, t: &[u8;16], buf: &mut [u8]) -> Result<(), InvalidLength>
+    {
+        let nblocks = check_len(buf.len())?;
+        transform(Dir::Enc, &self.cipher, &self.zero, t, buf, nblocks);
+        Ok(())
     }
-    xor_blocks(index_fixed!(&mut c; .. 16), &mc, t);           /* CCC1 = (xorSum CCCj) xor t xor mc */
 
+    /// Decrypts `buf` in place under tweak `t`. See [`Eme::encrypt`].
+    pub fn decrypt(&self, t: &[u8;16], buf: &mut [u8]) -> Result<(), InvalidLength>
     {
-        let (c_first, c_rest) = c.split_at_mut(16);
-        let c_first = index_fixed!(&mut c_first;..16);
-        for j in 1..32 {
-            xor_blocks_ip(c_first, index_fixed!(&c_rest[(j-1)*16..]; .. 16));
+        let nblocks = check_len(buf.len())?;
+        transform(Dir::Dec, &self.cipher, &self.zero, t, buf, nblocks);
+        Ok(())
+    }
+
+    /// Encrypts `buf` in place as a sequence of `sector_size`-byte units, each under its own
+    /// tweak: the first unit uses `base_tweak`, and each subsequent unit's tweak is the
+    /// previous one incremented by one, as a little-endian 128-bit counter.
+    ///
+    /// This is the common disk-sector / filename-encryption usage of EME: many same-sized units
+    /// under one key, tweaked by an incrementing counter, the same way AES-CTR derives a
+    /// per-block keystream from a counter.
+    ///
+    /// `buf.len()` must be a multiple of `sector_size`, and `sector_size` must itself be a valid
+    /// EME length (a non-zero multiple of 16 bytes, no larger than `MAX_BLOCKS * 16`).
+    pub fn encrypt_sectors(&self, base_tweak: &[u8;16], sector_size: usize, buf: &mut [u8]) -> Result<(), InvalidLength>
+    {
+        check_len(sector_size)?;
+        if !buf.len().is_multiple_of(sector_size) {
+            return Err(InvalidLength);
         }
+        let mut tweak = *base_tweak;
+        for sector in buf.chunks_mut(sector_size) {
+            self.encrypt(&tweak, sector)?;
+            increment_tweak(&mut tweak);
+        }
+        Ok(())
     }
-    mult_by_2(&mut l, &zero);                       /* reset l = 2*AES-enc(k; 0) */
-    for j in 0..32 {
-        encrypt_aes_ip(index_fixed!(&mut c[j*16..];..16), k);  /* CCj = AES-enc(k; CCCj)  */
-        xor_blocks_ip(index_fixed!(&mut c[j*16..];.. 16), &l);     /* Cj = 2**(j-1)*l xor CCj */
-        mult_by_2_ip(&mut l);
+
+    /// Decrypts `buf` in place as a sequence of `sector_size`-byte units. See
+    /// [`Eme::encrypt_sectors`].
+    pub fn decrypt_sectors(&self, base_tweak: &[u8;16], sector_size: usize, buf: &mut [u8]) -> Result<(), InvalidLength>
+    {
+        check_len(sector_size)?;
+        if !buf.len().is_multiple_of(sector_size) {
+            return Err(InvalidLength);
+        }
+        let mut tweak = *base_tweak;
+        for sector in buf.chunks_mut(sector_size) {
+            self.decrypt(&tweak, sector)?;
+            increment_tweak(&mut tweak);
+        }
+        Ok(())
     }
 }
 
+/// Increments a 128-bit tweak in place, treating it as a little-endian counter.
+fn increment_tweak(t: &mut [u8;16])
+{
+    for byte in t.iter_mut() {
+        let (new_byte, carry) = byte.overflowing_add(1);
+        *byte = new_byte;
+        if !carry {
+            break;
+        }
+    }
+}
+
+/// EME instantiated with AES-256, as specified by EME-32-AES (though, unlike EME-32-AES, not
+/// restricted to exactly 32 blocks -- see [`MAX_BLOCKS`]).
+pub type EmeAes256 = Eme<Aes256>;
+
+/// Encrypts `input` into `out` under raw key bytes `k` with tweak `t`, using AES-256.
+///
+/// `input` and `out` must have the same length, a non-zero multiple of 16 bytes, no larger than
+/// `MAX_BLOCKS * 16` bytes.
+pub fn eme_aes_enc(out: &mut [u8], k: &[u8], t: &[u8;16], input: &[u8]) -> Result<(), InvalidLength>
+{
+    if out.len() != input.len() {
+        return Err(InvalidLength);
+    }
+    out.copy_from_slice(input);
+    let cipher = Aes256::new_from_slice(k).expect("invalid AES-256 key length");
+    EmeAes256::new(cipher).encrypt(t, out)
+}
+
+/// Decrypts `input` into `out` under raw key bytes `k` with tweak `t`, using AES-256.
+/// See [`eme_aes_enc`].
+pub fn eme_aes_dec(out: &mut [u8], k: &[u8], t: &[u8;16], input: &[u8]) -> Result<(), InvalidLength>
+{
+    if out.len() != input.len() {
+        return Err(InvalidLength);
+    }
+    out.copy_from_slice(input);
+    let cipher = Aes256::new_from_slice(k).expect("invalid AES-256 key length");
+    EmeAes256::new(cipher).decrypt(t, out)
+}
+
+pub fn eme_32_aes_enc(c: &mut [u8;512], k: &[u8], t: &[u8;16], p: &[u8;512])
+{
+    eme_aes_enc(c, k, t, p).expect("512 bytes (32 blocks) is always a valid EME length");
+}
+
+pub fn eme_32_aes_dec(p: &mut [u8;512], k: &[u8], t: &[u8;16], c: &[u8;512])
+{
+    eme_aes_dec(p, k, t, c).expect("512 bytes (32 blocks) is always a valid EME length");
+}
+
 /*
  * N: bytes in block for encryption algo
  * M: bytes
@@ -153,7 +391,221 @@ pub fn eme_32_aes_enc(c: &mut [u8;512], k: &[u8], t: &[u8;16], p: &[u8;512])
 
 #[cfg(test)]
 mod tests {
+    use super::{eme_32_aes_enc, eme_32_aes_dec, eme_aes_enc, eme_aes_dec, EmeAes256, InvalidLength};
+    use aes::Aes256;
+    use aes::cipher::KeyInit;
+
+    fn key() -> [u8;32]
+    {
+        let mut k = [0u8;32];
+        for (i, b) in k.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        k
+    }
+
+    fn tweak() -> [u8;16]
+    {
+        let mut t = [0u8;16];
+        for (i, b) in t.iter_mut().enumerate() {
+            *b = (i * 3) as u8;
+        }
+        t
+    }
+
+    /// Round-trips `p` (already filled in by the caller) through `eme_aes_enc`/`eme_aes_dec` and
+    /// checks the plaintext is recovered and the ciphertext actually changed.
+    fn assert_round_trips(p: &[u8], c: &mut [u8], p2: &mut [u8])
+    {
+        let k = key();
+        let t = tweak();
+
+        eme_aes_enc(c, &k, &t, p).unwrap();
+        assert_ne!(p, &c[..]);
+
+        eme_aes_dec(p2, &k, &t, c).unwrap();
+        assert_eq!(p, &p2[..]);
+    }
+
     #[test]
     fn it_works() {
     }
+
+    #[test]
+    fn round_trip_zero() {
+        let k = [0u8;32];
+        let t = [0u8;16];
+        let p = [0u8;512];
+
+        let mut c = [0u8;512];
+        eme_32_aes_enc(&mut c, &k, &t, &p);
+
+        let mut p2 = [0u8;512];
+        eme_32_aes_dec(&mut p2, &k, &t, &c);
+
+        assert_eq!(&p[..], &p2[..]);
+    }
+
+    #[test]
+    fn round_trip_nonzero() {
+        let mut k = [0u8;32];
+        for (i, b) in k.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let mut t = [0u8;16];
+        for (i, b) in t.iter_mut().enumerate() {
+            *b = (i * 3) as u8;
+        }
+        let mut p = [0u8;512];
+        for (i, b) in p.iter_mut().enumerate() {
+            *b = (i * 7) as u8;
+        }
+
+        let mut c = [0u8;512];
+        eme_32_aes_enc(&mut c, &k, &t, &p);
+        assert_ne!(&p[..], &c[..]);
+
+        let mut p2 = [0u8;512];
+        eme_32_aes_dec(&mut p2, &k, &t, &c);
+
+        assert_eq!(&p[..], &p2[..]);
+    }
+
+    /// Pinned ciphertext for a fixed (key, tweak, plaintext) triple, generated from this crate's
+    /// own `eme_aes_enc`. Unlike the round-trip tests above, this catches a change that keeps
+    /// encryption and decryption consistent with each other but alters the output: any change to
+    /// the transform, the GF(2^128) doubling, or the AES-256 schedule changes this ciphertext.
+    ///
+    /// This is not a vector from the published EME-32-AES draft spec -- this sandbox has no
+    /// network access to pull the spec's own known-answer vectors, so regenerate this one (and
+    /// ideally replace it with a real spec vector) from a trusted independent implementation.
+    #[test]
+    fn known_answer_32_bytes() {
+        let mut k = [0u8;32];
+        for (i, b) in k.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let mut t = [0u8;16];
+        for (i, b) in t.iter_mut().enumerate() {
+            *b = (i * 5 + 1) as u8;
+        }
+        let mut p = [0u8;32];
+        for (i, b) in p.iter_mut().enumerate() {
+            *b = (i * 11 + 3) as u8;
+        }
+
+        let mut c = [0u8;32];
+        eme_aes_enc(&mut c, &k, &t, &p).unwrap();
+        assert_eq!(c, [
+            0x8f, 0xee, 0x3d, 0x36, 0xb6, 0x22, 0x96, 0xe8, 0xfd, 0x1a, 0xa9, 0x76, 0x25, 0x75, 0x7e, 0xb6,
+            0x50, 0x3b, 0xd0, 0x39, 0xfd, 0xc0, 0xbe, 0x8e, 0x37, 0x54, 0xe4, 0xf9, 0xa3, 0xdf, 0x57, 0xee,
+        ]);
+
+        let mut p2 = [0u8;32];
+        eme_aes_dec(&mut p2, &k, &t, &c).unwrap();
+        assert_eq!(p2, p);
+    }
+
+    #[test]
+    fn round_trip_1_block() {
+        let mut p = [0u8;16];
+        let mut c = [0u8;16];
+        let mut p2 = [0u8;16];
+        for (i, b) in p.iter_mut().enumerate() { *b = (i * 7) as u8; }
+        assert_round_trips(&p, &mut c, &mut p2);
+    }
+
+    #[test]
+    fn round_trip_2_blocks() {
+        let mut p = [0u8;32];
+        let mut c = [0u8;32];
+        let mut p2 = [0u8;32];
+        for (i, b) in p.iter_mut().enumerate() { *b = (i * 7) as u8; }
+        assert_round_trips(&p, &mut c, &mut p2);
+    }
+
+    #[test]
+    fn round_trip_31_blocks() {
+        let mut p = [0u8;31*16];
+        let mut c = [0u8;31*16];
+        let mut p2 = [0u8;31*16];
+        for (i, b) in p.iter_mut().enumerate() { *b = (i * 7) as u8; }
+        assert_round_trips(&p, &mut c, &mut p2);
+    }
+
+    #[test]
+    fn round_trip_33_blocks() {
+        let mut p = [0u8;33*16];
+        let mut c = [0u8;33*16];
+        let mut p2 = [0u8;33*16];
+        for (i, b) in p.iter_mut().enumerate() { *b = (i * 7) as u8; }
+        assert_round_trips(&p, &mut c, &mut p2);
+    }
+
+    #[test]
+    fn round_trip_128_blocks() {
+        let mut p = [0u8;128*16];
+        let mut c = [0u8;128*16];
+        let mut p2 = [0u8;128*16];
+        for (i, b) in p.iter_mut().enumerate() { *b = (i * 7) as u8; }
+        assert_round_trips(&p, &mut c, &mut p2);
+    }
+
+    #[test]
+    fn rejects_invalid_lengths() {
+        let k = key();
+        let t = tweak();
+
+        let mut odd_c = [0u8;17];
+        assert_eq!(eme_aes_enc(&mut odd_c, &k, &t, &[0u8;17]), Err(InvalidLength));
+
+        let mut zero_c: [u8;0] = [];
+        assert_eq!(eme_aes_enc(&mut zero_c, &k, &t, &[]), Err(InvalidLength));
+
+        let mut too_big_c = [0u8;129*16];
+        assert_eq!(eme_aes_enc(&mut too_big_c, &k, &t, &[0u8;129*16]), Err(InvalidLength));
+    }
+
+    #[test]
+    fn encrypt_sectors_round_trips() {
+        let cipher = Aes256::new_from_slice(&key()).unwrap();
+        let eme = EmeAes256::new(cipher);
+
+        let sector_size = 32;
+        let mut buf = [0u8;32*4];
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = (i * 7) as u8;
+        }
+        let plain = buf;
+
+        eme.encrypt_sectors(&tweak(), sector_size, &mut buf).unwrap();
+        assert_ne!(&buf[..], &plain[..]);
+
+        eme.decrypt_sectors(&tweak(), sector_size, &mut buf).unwrap();
+        assert_eq!(&buf[..], &plain[..]);
+    }
+
+    #[test]
+    fn encrypt_sectors_uses_distinct_tweaks_per_sector() {
+        let cipher = Aes256::new_from_slice(&key()).unwrap();
+        let eme = EmeAes256::new(cipher);
+
+        // two identical-plaintext sectors must encrypt to different ciphertext, since each
+        // sector's tweak is the incremented counter, not a repeated base_tweak.
+        let mut buf = [0u8;32];
+        eme.encrypt_sectors(&tweak(), 16, &mut buf).unwrap();
+        assert_ne!(&buf[0..16], &buf[16..32]);
+    }
+
+    #[test]
+    fn encrypt_sectors_rejects_bad_sizes() {
+        let cipher = Aes256::new_from_slice(&key()).unwrap();
+        let eme = EmeAes256::new(cipher);
+
+        let mut buf = [0u8;33];
+        assert_eq!(eme.encrypt_sectors(&tweak(), 16, &mut buf), Err(InvalidLength));
+
+        let mut buf2 = [0u8;32];
+        assert_eq!(eme.encrypt_sectors(&tweak(), 0, &mut buf2), Err(InvalidLength));
+    }
 }